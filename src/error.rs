@@ -0,0 +1,24 @@
+use pinocchio::program_error::ProgramError;
+
+/// Vault-specific failure reasons, surfaced as `ProgramError::Custom` so
+/// clients can tell a misconfigured PDA/payer apart from a generic
+/// account-ownership failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultError {
+    /// The supplied vault account does not match the canonical
+    /// `[b"vault", owner]` PDA for this program.
+    InvalidVaultPda,
+    /// The bump stored in an existing vault's state no longer matches the
+    /// canonical derivation (the vault was not created by this program).
+    BumpMismatch,
+    /// The owner account must sign so it can authorize creating the vault.
+    PayerMustSign,
+    /// The owner account must be writable so it can pay for vault creation.
+    PayerMustBeWritable,
+}
+
+impl From<VaultError> for ProgramError {
+    fn from(err: VaultError) -> Self {
+        ProgramError::Custom(err as u32)
+    }
+}
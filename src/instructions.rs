@@ -2,16 +2,20 @@ use core::convert::TryFrom;
 use core::mem::size_of;
 use pinocchio::{
     account_info::AccountInfo,
-    instruction::{Seed, Signer},
+    cpi::invoke_signed,
+    instruction::{AccountMeta, Instruction, Seed, Signer},
     program_error::ProgramError,
-    pubkey::{find_program_address, Pubkey},
-    sysvars::{rent::Rent, Sysvar},
+    pubkey::{create_program_address, find_program_address, Pubkey},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     ProgramResult,
 };
 use pinocchio_log::log;
 use pinocchio_system::instructions::{CreateAccount, Transfer as SystemTransfer};
+use pinocchio_token::instructions::Transfer as TokenTransfer;
 use shank::ShankInstruction;
 
+use crate::error::VaultError;
+use crate::state::{self, VaultState};
 
 #[derive(ShankInstruction)]
 pub enum _Instruction {
@@ -36,7 +40,85 @@ pub enum _Instruction {
     )]
     #[account(1, writable, name = "vault", desc = "Vault PDA itself")]
     #[account(2, name = "program", desc = "Program Address")]
-    Withdraw {},
+    Withdraw { amount: u64 },
+
+    #[account(
+        0,
+        name = "owner",
+        writable,
+        signer,
+        desc = "signer of the vault tx and vault owner"
+    )]
+    #[account(1, writable, name = "vault", desc = "the vault account itself")]
+    #[account(2, writable, name = "source", desc = "owner's token account")]
+    #[account(
+        3,
+        writable,
+        name = "vault_token_account",
+        desc = "vault's associated token account (PDA)"
+    )]
+    #[account(4, name = "mint", desc = "the SPL token mint")]
+    #[account(5, name = "token_program", desc = "SPL token program address")]
+    DepositToken { amount: u64 },
+
+    #[account(
+        0,
+        signer,
+        writable,
+        name = "owner",
+        desc = "Vault owner and authority"
+    )]
+    #[account(1, writable, name = "vault", desc = "Vault PDA itself")]
+    #[account(
+        2,
+        writable,
+        name = "vault_token_account",
+        desc = "vault's associated token account (PDA)"
+    )]
+    #[account(3, writable, name = "destination", desc = "owner's token account")]
+    #[account(4, name = "mint", desc = "the SPL token mint")]
+    #[account(5, name = "token_program", desc = "SPL token program address")]
+    WithdrawToken { amount: u64 },
+
+    #[account(
+        0,
+        signer,
+        writable,
+        name = "owner",
+        desc = "Vault owner and authority"
+    )]
+    #[account(1, writable, name = "vault", desc = "Vault PDA itself")]
+    AddWhitelist {
+        program_id: Pubkey,
+        destination: Pubkey,
+    },
+
+    #[account(
+        0,
+        signer,
+        writable,
+        name = "owner",
+        desc = "Vault owner and authority"
+    )]
+    #[account(1, writable, name = "vault", desc = "Vault PDA itself")]
+    RemoveWhitelist { program_id: Pubkey },
+
+    // No owner signature: a whitelisted program authorizes itself by being
+    // the direct CPI caller (read from `instructions_sysvar`), which is what
+    // lets it pull funds on the owner's behalf without a live owner signature.
+    #[account(0, writable, name = "vault", desc = "Vault PDA itself")]
+    #[account(
+        1,
+        name = "instructions_sysvar",
+        desc = "Instructions sysvar, used to read the direct caller's program id"
+    )]
+    #[account(
+        2,
+        writable,
+        name = "relay_accounts",
+        desc = "variable-length tail: every remaining account required by the relayed instruction, vault included"
+    )]
+    RelayWithdraw {},
 }
 
 fn parse_amount(data: &[u8]) -> Result<u64, ProgramError> {
@@ -53,20 +135,95 @@ fn parse_amount(data: &[u8]) -> Result<u64, ProgramError> {
     Ok(amount)
 }
 
+/// Canonical vault PDA: `[b"vault", owner]`. This is the single seed set
+/// used both to create a vault and to validate one, so a created vault can
+/// never diverge from the address a later instruction expects.
 fn derive_vault_pda(owner: &AccountInfo) -> (Pubkey, u8) {
-    find_program_address(&[b"no-std-vault", owner.key().as_ref()], &crate::ID)
+    find_program_address(&[b"vault", owner.key().as_ref()], &crate::ID)
 }
 
-fn check_vault_existence(owner: &AccountInfo, vault: &AccountInfo) -> ProgramResult {
+fn derive_vault_token_pda(owner: &AccountInfo) -> (Pubkey, u8) {
+    find_program_address(&[b"vault-token", owner.key().as_ref()], &crate::ID)
+}
+
+/// Reads the mint recorded in an SPL token account. The mint is the first 32
+/// bytes of a `spl_token::state::Account`, a layout fixed by the token
+/// program and stable across token account sizes (including token-2022's
+/// trailing extensions).
+fn token_account_mint(token_account: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    let data = token_account.try_borrow_data()?;
+    if data.len() < size_of::<Pubkey>() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut mint: Pubkey = [0u8; 32];
+    mint.copy_from_slice(&data[..size_of::<Pubkey>()]);
+    Ok(mint)
+}
+
+/// Validates `vault` against the canonical PDA for `owner` and returns the
+/// bump to sign with.
+///
+/// For a not-yet-created vault this also enforces that `owner` is a
+/// writable signer, since it is about to pay for `CreateAccount`, and the
+/// bump comes from a fresh `find_program_address` search since none is
+/// stored yet. For an existing vault the stored `VaultState::bump` is
+/// reused with `create_program_address` instead - a single hash rather than
+/// a bump search - so a vault account that was not actually created by this
+/// program (or whose stored bump no longer reconstructs its address) is
+/// rejected rather than trusted on the strength of its `owner` field alone.
+fn validate_vault_account(owner: &AccountInfo, vault: &AccountInfo) -> Result<u8, ProgramError> {
     if !owner.is_signer() {
-        return Err(ProgramError::InvalidAccountOwner);
+        return Err(VaultError::PayerMustSign.into());
     }
 
     if vault.lamports() == 0 {
-        const DISCRIMINATOR: usize = 8;
+        if !owner.is_writable() {
+            return Err(VaultError::PayerMustBeWritable.into());
+        }
+
+        let (expected_vault_pda, bump) = derive_vault_pda(owner);
+        if vault.key() != &expected_vault_pda {
+            return Err(VaultError::InvalidVaultPda.into());
+        }
+
+        return Ok(bump);
+    }
+
+    if !vault.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let state = VaultState::load(vault)?;
+    if &state.owner != owner.key() {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let expected_vault_pda = create_program_address(
+        &[
+            b"vault",
+            owner.key().as_ref(),
+            core::slice::from_ref(&state.bump),
+        ],
+        &crate::ID,
+    )
+    .map_err(|_| ProgramError::from(VaultError::BumpMismatch))?;
+
+    if vault.key() != &expected_vault_pda {
+        return Err(VaultError::BumpMismatch.into());
+    }
+
+    Ok(state.bump)
+}
 
-        let (_pda, bump) = derive_vault_pda(owner);
+fn check_vault_existence(
+    owner: &AccountInfo,
+    vault: &AccountInfo,
+    vesting: Option<(i64, i64)>,
+) -> ProgramResult {
+    let bump = validate_vault_account(owner, vault)?;
 
+    if vault.lamports() == 0 {
         let seeds = [
             Seed::from(b"vault".as_ref()),
             Seed::from(owner.key().as_ref()),
@@ -75,25 +232,24 @@ fn check_vault_existence(owner: &AccountInfo, vault: &AccountInfo) -> ProgramRes
 
         let signer = Signer::from(&seeds);
 
-        let data_len: usize = DISCRIMINATOR + size_of::<u64>();
-
-        let required_lamports = Rent::get()?.minimum_balance(data_len);
+        let required_lamports = Rent::get()?.minimum_balance(state::LEN);
 
         CreateAccount {
             from: owner,
             to: vault,
             lamports: required_lamports,
-            space: data_len as u64,
+            space: state::LEN as u64,
             owner: &crate::ID,
         }
         .invoke_signed(&[signer])?;
 
+        let now = Clock::get()?.unix_timestamp;
+        let (cliff_ts, end_ts) = vesting.unwrap_or((now, now));
+
+        VaultState::new(*owner.key(), bump, now, cliff_ts, end_ts).store(vault)?;
+
         log!("Vault now active on chain!");
     } else {
-        if !vault.is_owned_by(&crate::ID) {
-            return Err(ProgramError::InvalidAccountOwner);
-        }
-
         log!("Vault already exists!");
     }
     Ok(())
@@ -103,6 +259,11 @@ pub struct Deposit<'a> {
     pub owner: &'a AccountInfo,
     pub vault: &'a AccountInfo,
     pub amount: u64,
+    /// `(cliff_ts, end_ts)` for a linear vesting schedule, only honored the
+    /// first time the vault is created. Absent on that first deposit means
+    /// the vault has no schedule and its deposits are fully liquid, matching
+    /// the old behavior; later deposits join whatever schedule the vault has.
+    pub vesting: Option<(i64, i64)>,
 }
 
 impl<'a> Deposit<'a> {
@@ -112,9 +273,10 @@ impl<'a> Deposit<'a> {
             owner,
             vault,
             amount,
+            vesting,
         } = self;
 
-        check_vault_existence(owner, vault)?;
+        check_vault_existence(owner, vault, vesting)?;
 
         SystemTransfer {
             from: owner,
@@ -123,6 +285,17 @@ impl<'a> Deposit<'a> {
         }
         .invoke()?;
 
+        let mut state = VaultState::load_mut(vault)?;
+        state.total_deposited = state
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        state.deposit_count = state
+            .deposit_count
+            .checked_add(1)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        state.store(vault)?;
+
         log!(" {} funds moved to vault!", amount);
 
         Ok(())
@@ -141,12 +314,32 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Deposit<'a> {
 
         let owner = &accounts[0];
         let vault = &accounts[1];
-        let amount = parse_amount(data)?;
+        let amount = parse_amount(&data[..size_of::<u64>().min(data.len())])?;
+
+        let vesting = match data.len() {
+            n if n == size_of::<u64>() => None,
+            n if n == size_of::<u64>() + size_of::<i64>() * 2 => {
+                let cliff_ts = i64::from_le_bytes(
+                    data[size_of::<u64>()..size_of::<u64>() + size_of::<i64>()]
+                        .try_into()
+                        .unwrap(),
+                );
+                let end_ts = i64::from_le_bytes(
+                    data[size_of::<u64>() + size_of::<i64>()
+                        ..size_of::<u64>() + size_of::<i64>() * 2]
+                        .try_into()
+                        .unwrap(),
+                );
+                Some((cliff_ts, end_ts))
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
 
         Ok(Self {
             owner,
             vault,
             amount,
+            vesting,
         })
     }
 }
@@ -154,27 +347,20 @@ impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Deposit<'a> {
 pub struct Withdraw<'a> {
     pub owner: &'a AccountInfo,
     pub vault: &'a AccountInfo,
+    pub amount: u64,
 }
 
 impl<'a> Withdraw<'a> {
     pub const DISCRIMINATOR: &'a u8 = &1;
 
     pub fn process(self) -> ProgramResult {
-        let Withdraw { owner, vault } = self;
-
-        if !owner.is_signer() {
-            return Err(ProgramError::InvalidAccountOwner);
-        }
-
-        if !vault.is_owned_by(&crate::ID) {
-            return Err(ProgramError::InvalidAccountOwner);
-        }
+        let Withdraw {
+            owner,
+            vault,
+            amount,
+        } = self;
 
-        let (expected_vault_pda, _bump) = derive_vault_pda(owner);
-
-        if vault.key() != &expected_vault_pda {
-            return Err(ProgramError::InvalidAccountData);
-        }
+        validate_vault_account(owner, vault)?;
 
         let data_len = vault.data_len();
         let minimum_bal = Rent::get()?.minimum_balance(data_len);
@@ -184,7 +370,15 @@ impl<'a> Withdraw<'a> {
             return Err(ProgramError::InsufficientFunds);
         }
 
-        let amount = current_balance - minimum_bal;
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut state = VaultState::load_mut(vault)?;
+        let unlocked = state.vested(now).saturating_sub(state.released);
+        let headroom = current_balance - minimum_bal;
+
+        if amount > unlocked || amount > headroom {
+            return Err(ProgramError::InsufficientFunds);
+        }
 
         {
             let mut vault_lamports = vault.try_borrow_mut_lamports()?;
@@ -195,27 +389,547 @@ impl<'a> Withdraw<'a> {
         }
 
         {
-            let mut owner_lamports = vault.try_borrow_mut_lamports()?;
+            let mut owner_lamports = owner.try_borrow_mut_lamports()?;
             *owner_lamports = owner_lamports
                 .checked_add(amount)
                 .ok_or(ProgramError::InsufficientFunds)?;
         }
 
+        state.released = state
+            .released
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        state.store(vault)?;
+
         log!("{} lamports withdrawn from vault", amount);
 
         Ok(())
     }
 }
 
-impl<'a> TryFrom<&'a [AccountInfo]> for Withdraw<'a> {
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for Withdraw<'a> {
     type Error = ProgramError;
 
-    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+    fn try_from(value: (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (data, accounts) = value;
+
         if accounts.len() < 2 {
             return Err(ProgramError::NotEnoughAccountKeys);
         }
         let owner = &accounts[0];
         let vault = &accounts[1];
-        Ok(Self { owner, vault })
+        let amount = parse_amount(data)?;
+        Ok(Self {
+            owner,
+            vault,
+            amount,
+        })
+    }
+}
+
+pub struct DepositToken<'a> {
+    pub owner: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub source: &'a AccountInfo,
+    pub vault_token_account: &'a AccountInfo,
+    pub mint: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub amount: u64,
+}
+
+impl<'a> DepositToken<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &2;
+
+    pub fn process(self) -> ProgramResult {
+        let DepositToken {
+            owner,
+            vault,
+            source,
+            vault_token_account,
+            mint: _,
+            token_program: _,
+            amount,
+        } = self;
+
+        check_vault_existence(owner, vault, None)?;
+
+        TokenTransfer {
+            from: source,
+            to: vault_token_account,
+            authority: owner,
+            amount,
+        }
+        .invoke()?;
+
+        let mut state = VaultState::load_mut(vault)?;
+        state.token_total_deposited = state
+            .token_total_deposited
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        state.deposit_count = state
+            .deposit_count
+            .checked_add(1)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        state.store(vault)?;
+
+        log!("{} tokens deposited to vault", amount);
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for DepositToken<'a> {
+    type Error = ProgramError;
+
+    fn try_from(value: (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (data, accounts) = value;
+
+        if accounts.len() < 6 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let owner = &accounts[0];
+        let vault = &accounts[1];
+        let source = &accounts[2];
+        let vault_token_account = &accounts[3];
+        let mint = &accounts[4];
+        let token_program = &accounts[5];
+        let amount = parse_amount(data)?;
+
+        let (expected_vault_token_pda, _bump) = derive_vault_token_pda(owner);
+        if vault_token_account.key() != &expected_vault_token_pda {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if token_account_mint(vault_token_account)? != *mint.key()
+            || token_account_mint(source)? != *mint.key()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            owner,
+            vault,
+            source,
+            vault_token_account,
+            mint,
+            token_program,
+            amount,
+        })
+    }
+}
+
+pub struct WithdrawToken<'a> {
+    pub owner: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub vault_token_account: &'a AccountInfo,
+    pub destination: &'a AccountInfo,
+    pub mint: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+    pub amount: u64,
+}
+
+impl<'a> WithdrawToken<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    pub fn process(self) -> ProgramResult {
+        let WithdrawToken {
+            owner,
+            vault,
+            vault_token_account,
+            destination,
+            mint: _,
+            token_program: _,
+            amount,
+        } = self;
+
+        let bump = validate_vault_account(owner, vault)?;
+
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut state = VaultState::load_mut(vault)?;
+        let unlocked = state.token_vested(now).saturating_sub(state.token_released);
+
+        if amount > unlocked {
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        let seeds = [
+            Seed::from(b"vault".as_ref()),
+            Seed::from(owner.key().as_ref()),
+            Seed::from(core::slice::from_ref(&bump)),
+        ];
+        let signer = Signer::from(&seeds);
+
+        TokenTransfer {
+            from: vault_token_account,
+            to: destination,
+            authority: vault,
+            amount,
+        }
+        .invoke_signed(&[signer])?;
+
+        state.token_released = state
+            .token_released
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        state.store(vault)?;
+
+        log!("{} tokens withdrawn from vault", amount);
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for WithdrawToken<'a> {
+    type Error = ProgramError;
+
+    fn try_from(value: (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (data, accounts) = value;
+
+        if accounts.len() < 6 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let owner = &accounts[0];
+        let vault = &accounts[1];
+        let vault_token_account = &accounts[2];
+        let destination = &accounts[3];
+        let mint = &accounts[4];
+        let token_program = &accounts[5];
+        let amount = parse_amount(data)?;
+
+        let (expected_vault_token_pda, _bump) = derive_vault_token_pda(owner);
+        if vault_token_account.key() != &expected_vault_token_pda {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if token_account_mint(vault_token_account)? != *mint.key()
+            || token_account_mint(destination)? != *mint.key()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            owner,
+            vault,
+            vault_token_account,
+            destination,
+            mint,
+            token_program,
+            amount,
+        })
+    }
+}
+
+fn only_owner(owner: &AccountInfo, vault: &AccountInfo) -> Result<VaultState, ProgramError> {
+    if !owner.is_signer() {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    if !vault.is_owned_by(&crate::ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let state = VaultState::load_mut(vault)?;
+    if &state.owner != owner.key() {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    Ok(state)
+}
+
+pub struct AddWhitelist<'a> {
+    pub owner: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub program_id: Pubkey,
+    /// The only account `RelayWithdraw` may pay `program_id` out to.
+    pub destination: Pubkey,
+}
+
+impl<'a> AddWhitelist<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    pub fn process(self) -> ProgramResult {
+        let AddWhitelist {
+            owner,
+            vault,
+            program_id,
+            destination,
+        } = self;
+
+        let mut state = only_owner(owner, vault)?;
+        state.add_to_whitelist(program_id, destination)?;
+        state.store(vault)?;
+
+        log!("Whitelisted a new delegate program");
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for AddWhitelist<'a> {
+    type Error = ProgramError;
+
+    fn try_from(value: (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (data, accounts) = value;
+
+        if accounts.len() < 2 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        if data.len() != size_of::<Pubkey>() * 2 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let owner = &accounts[0];
+        let vault = &accounts[1];
+
+        let mut program_id: Pubkey = [0u8; 32];
+        program_id.copy_from_slice(&data[..size_of::<Pubkey>()]);
+
+        let mut destination: Pubkey = [0u8; 32];
+        destination.copy_from_slice(&data[size_of::<Pubkey>()..]);
+
+        Ok(Self {
+            owner,
+            vault,
+            program_id,
+            destination,
+        })
+    }
+}
+
+pub struct RemoveWhitelist<'a> {
+    pub owner: &'a AccountInfo,
+    pub vault: &'a AccountInfo,
+    pub program_id: Pubkey,
+}
+
+impl<'a> RemoveWhitelist<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    pub fn process(self) -> ProgramResult {
+        let RemoveWhitelist {
+            owner,
+            vault,
+            program_id,
+        } = self;
+
+        let mut state = only_owner(owner, vault)?;
+        state.remove_from_whitelist(&program_id);
+        state.store(vault)?;
+
+        log!("Removed a delegate program from the whitelist");
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for RemoveWhitelist<'a> {
+    type Error = ProgramError;
+
+    fn try_from(value: (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (data, accounts) = value;
+
+        if accounts.len() < 2 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        if data.len() != size_of::<Pubkey>() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let owner = &accounts[0];
+        let vault = &accounts[1];
+
+        let mut program_id: Pubkey = [0u8; 32];
+        program_id.copy_from_slice(data);
+
+        Ok(Self {
+            owner,
+            vault,
+            program_id,
+        })
+    }
+}
+
+/// Caps the number of accounts a single `RelayWithdraw` can forward to the
+/// whitelisted target program, mirroring `state::MAX_WHITELIST`'s fixed
+/// capacity so the vault never needs a heap allocation.
+const MAX_RELAY_ACCOUNTS: usize = 8;
+
+/// Reads the program id of the instruction at `index` out of the raw
+/// `Instructions` sysvar buffer. Mirrors the wire format written by the
+/// runtime: a `u16` instruction count, a `u16` offset per instruction, and
+/// at each offset a `u16` account count, that many `(flags: u8, pubkey)`
+/// entries, then the 32-byte program id.
+fn load_instruction_program_id(data: &[u8], index: usize) -> Result<Pubkey, ProgramError> {
+    let num_instructions = u16::from_le_bytes(
+        data.get(0..2)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .try_into()
+            .unwrap(),
+    ) as usize;
+
+    if index >= num_instructions {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let offset_pos = 2 + index * 2;
+    let mut cursor = u16::from_le_bytes(
+        data.get(offset_pos..offset_pos + 2)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .try_into()
+            .unwrap(),
+    ) as usize;
+
+    let num_accounts = u16::from_le_bytes(
+        data.get(cursor..cursor + 2)
+            .ok_or(ProgramError::InvalidAccountData)?
+            .try_into()
+            .unwrap(),
+    ) as usize;
+    cursor += 2 + num_accounts * (1 + size_of::<Pubkey>());
+
+    let mut program_id: Pubkey = [0u8; 32];
+    program_id.copy_from_slice(
+        data.get(cursor..cursor + size_of::<Pubkey>())
+            .ok_or(ProgramError::InvalidAccountData)?,
+    );
+    Ok(program_id)
+}
+
+/// The program id of the instruction directly invoking this one, read from
+/// the `Instructions` sysvar rather than a caller-supplied account. This is
+/// what lets a whitelisted program call `RelayWithdraw` on the owner's
+/// behalf purely by virtue of being the CPI caller, with no live signature
+/// from the owner and no caller-claimed `target_program` to spoof.
+///
+/// The sysvar's trailing `u16` is the index of the currently-executing
+/// top-level instruction, and the runtime does not advance it across CPI -
+/// so the program that CPI'd into us is the one *at* that index, not the
+/// one before it.
+fn caller_program_id(instructions_sysvar: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    let data = instructions_sysvar.try_borrow_data()?;
+
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let current_index = u16::from_le_bytes(data[data.len() - 2..].try_into().unwrap()) as usize;
+
+    load_instruction_program_id(&data, current_index)
+}
+
+pub struct RelayWithdraw<'a> {
+    pub vault: &'a AccountInfo,
+    pub instructions_sysvar: &'a AccountInfo,
+    pub relay_accounts: &'a [AccountInfo],
+    pub data: &'a [u8],
+}
+
+impl<'a> RelayWithdraw<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    pub fn process(self) -> ProgramResult {
+        let RelayWithdraw {
+            vault,
+            instructions_sysvar,
+            relay_accounts,
+            data,
+        } = self;
+
+        if !vault.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let state = VaultState::load(vault)?;
+        let caller = caller_program_id(instructions_sysvar)?;
+
+        // The caller must be whitelisted, and may only move funds to the one
+        // destination the owner approved for it - never an arbitrary address
+        // chosen by the relayed instruction.
+        let destination = state
+            .approved_destination(&caller)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        // The vault must be among the relayed accounts so it can sign the CPI
+        // as the source of funds; which account the target program actually
+        // debits is not checked here.
+        if !relay_accounts
+            .iter()
+            .any(|account| account.key() == vault.key())
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut metas = [AccountMeta::readonly(vault.key()); MAX_RELAY_ACCOUNTS];
+        for (i, account) in relay_accounts.iter().enumerate() {
+            let is_vault = account.key() == vault.key();
+
+            // Only the vault PDA itself may sign; a caller-claimed signer
+            // would let the relay impersonate any account it names.
+            if account.is_signer() && !is_vault {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            // Only the vault (source) and the whitelist-approved destination
+            // may be writable; every other account can be read but not moved.
+            if account.is_writable() && !is_vault && account.key() != &destination {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            metas[i] = match (is_vault, account.is_writable()) {
+                (true, _) => AccountMeta::writable_signer(account.key()),
+                (false, true) => AccountMeta::writable(account.key()),
+                (false, false) => AccountMeta::readonly(account.key()),
+            };
+        }
+
+        let instruction = Instruction {
+            program_id: &caller,
+            accounts: &metas[..relay_accounts.len()],
+            data,
+        };
+
+        let seeds = [
+            Seed::from(b"vault".as_ref()),
+            Seed::from(state.owner.as_ref()),
+            Seed::from(core::slice::from_ref(&state.bump)),
+        ];
+        let signer = Signer::from(&seeds);
+
+        invoke_signed(&instruction, relay_accounts, &[signer])?;
+
+        log!("Relayed a whitelisted withdrawal via CPI");
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<(&'a [u8], &'a [AccountInfo])> for RelayWithdraw<'a> {
+    type Error = ProgramError;
+
+    fn try_from(value: (&'a [u8], &'a [AccountInfo])) -> Result<Self, Self::Error> {
+        let (data, accounts) = value;
+
+        if accounts.len() < 2 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let vault = &accounts[0];
+        let instructions_sysvar = &accounts[1];
+        let relay_accounts = &accounts[2..];
+
+        if relay_accounts.len() > MAX_RELAY_ACCOUNTS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            vault,
+            instructions_sysvar,
+            relay_accounts,
+            data,
+        })
     }
 }
@@ -5,7 +5,9 @@ use pinocchio::{
     ProgramResult,
 };
 
+mod error;
 mod instructions;
+mod state;
 use instructions::*;
 
 use pinocchio_pubkey::declare_id;
@@ -21,7 +23,22 @@ fn process_instruction(
 ) -> ProgramResult {
     match data.split_first() {
         Some((Deposit::DISCRIMINATOR, data)) => Deposit::try_from((data, accounts))?.process(),
-        Some((Withdraw::DISCRIMINATOR, _)) => Withdraw::try_from(accounts)?.process(),
+        Some((Withdraw::DISCRIMINATOR, data)) => Withdraw::try_from((data, accounts))?.process(),
+        Some((DepositToken::DISCRIMINATOR, data)) => {
+            DepositToken::try_from((data, accounts))?.process()
+        }
+        Some((WithdrawToken::DISCRIMINATOR, data)) => {
+            WithdrawToken::try_from((data, accounts))?.process()
+        }
+        Some((AddWhitelist::DISCRIMINATOR, data)) => {
+            AddWhitelist::try_from((data, accounts))?.process()
+        }
+        Some((RemoveWhitelist::DISCRIMINATOR, data)) => {
+            RemoveWhitelist::try_from((data, accounts))?.process()
+        }
+        Some((RelayWithdraw::DISCRIMINATOR, data)) => {
+            RelayWithdraw::try_from((data, accounts))?.process()
+        }
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -0,0 +1,275 @@
+use core::mem::size_of;
+use pinocchio::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+
+/// Marks the first 8 bytes of a vault account so the program can reject
+/// foreign or stale account data instead of silently misreading it.
+pub const DISCRIMINATOR: [u8; 8] = *b"NSVAULT1";
+
+const OWNER_OFFSET: usize = DISCRIMINATOR.len();
+const BUMP_OFFSET: usize = OWNER_OFFSET + size_of::<Pubkey>();
+const TOTAL_DEPOSITED_OFFSET: usize = BUMP_OFFSET + size_of::<u8>();
+const DEPOSIT_COUNT_OFFSET: usize = TOTAL_DEPOSITED_OFFSET + size_of::<u64>();
+const RELEASED_OFFSET: usize = DEPOSIT_COUNT_OFFSET + size_of::<u32>();
+const TOKEN_TOTAL_DEPOSITED_OFFSET: usize = RELEASED_OFFSET + size_of::<u64>();
+const TOKEN_RELEASED_OFFSET: usize = TOKEN_TOTAL_DEPOSITED_OFFSET + size_of::<u64>();
+const START_TS_OFFSET: usize = TOKEN_RELEASED_OFFSET + size_of::<u64>();
+const CLIFF_TS_OFFSET: usize = START_TS_OFFSET + size_of::<i64>();
+const END_TS_OFFSET: usize = CLIFF_TS_OFFSET + size_of::<i64>();
+const WHITELIST_LEN_OFFSET: usize = END_TS_OFFSET + size_of::<i64>();
+const WHITELIST_PROGRAMS_OFFSET: usize = WHITELIST_LEN_OFFSET + size_of::<u8>();
+
+/// Maximum number of delegate program ids a vault can whitelist for
+/// `RelayWithdraw`, each paired with the one destination it may pay out to.
+pub const MAX_WHITELIST: usize = 8;
+
+const WHITELIST_DESTINATIONS_OFFSET: usize =
+    WHITELIST_PROGRAMS_OFFSET + MAX_WHITELIST * size_of::<Pubkey>();
+
+/// Total size of a vault account's data, discriminator included.
+pub const LEN: usize = WHITELIST_DESTINATIONS_OFFSET + MAX_WHITELIST * size_of::<Pubkey>();
+
+/// On-chain state for a vault account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VaultState {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub total_deposited: u64,
+    pub deposit_count: u32,
+    pub released: u64,
+    /// SPL-token deposits, tracked separately from `total_deposited` so a
+    /// token transfer never inflates the lamport vesting schedule's
+    /// numerator.
+    pub token_total_deposited: u64,
+    pub token_released: u64,
+    pub start_ts: i64,
+    pub cliff_ts: i64,
+    pub end_ts: i64,
+    pub whitelist_len: u8,
+    pub whitelist_programs: [Pubkey; MAX_WHITELIST],
+    /// The one destination each whitelisted program may pay a
+    /// `RelayWithdraw` out to, indexed in lockstep with `whitelist_programs`.
+    pub whitelist_destinations: [Pubkey; MAX_WHITELIST],
+}
+
+impl VaultState {
+    pub fn new(owner: Pubkey, bump: u8, start_ts: i64, cliff_ts: i64, end_ts: i64) -> Self {
+        Self {
+            owner,
+            bump,
+            total_deposited: 0,
+            deposit_count: 0,
+            released: 0,
+            token_total_deposited: 0,
+            token_released: 0,
+            start_ts,
+            cliff_ts,
+            end_ts,
+            whitelist_len: 0,
+            whitelist_programs: [[0u8; 32]; MAX_WHITELIST],
+            whitelist_destinations: [[0u8; 32]; MAX_WHITELIST],
+        }
+    }
+
+    pub fn is_whitelisted(&self, program_id: &Pubkey) -> bool {
+        self.whitelist_programs[..self.whitelist_len as usize]
+            .iter()
+            .any(|entry| entry == program_id)
+    }
+
+    /// The approved payout destination for a whitelisted `program_id`, if
+    /// any.
+    pub fn approved_destination(&self, program_id: &Pubkey) -> Option<Pubkey> {
+        self.whitelist_programs[..self.whitelist_len as usize]
+            .iter()
+            .position(|entry| entry == program_id)
+            .map(|pos| self.whitelist_destinations[pos])
+    }
+
+    pub fn add_to_whitelist(
+        &mut self,
+        program_id: Pubkey,
+        destination: Pubkey,
+    ) -> Result<(), ProgramError> {
+        let len = self.whitelist_len as usize;
+
+        if let Some(pos) = self.whitelist_programs[..len]
+            .iter()
+            .position(|entry| entry == &program_id)
+        {
+            self.whitelist_destinations[pos] = destination;
+            return Ok(());
+        }
+
+        if len >= MAX_WHITELIST {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        self.whitelist_programs[len] = program_id;
+        self.whitelist_destinations[len] = destination;
+        self.whitelist_len += 1;
+        Ok(())
+    }
+
+    pub fn remove_from_whitelist(&mut self, program_id: &Pubkey) {
+        let len = self.whitelist_len as usize;
+        if let Some(pos) = self.whitelist_programs[..len]
+            .iter()
+            .position(|entry| entry == program_id)
+        {
+            self.whitelist_programs[pos..len].rotate_left(1);
+            self.whitelist_destinations[pos..len].rotate_left(1);
+            self.whitelist_len -= 1;
+        }
+    }
+
+    fn linear_unlock(total: u64, start_ts: i64, cliff_ts: i64, end_ts: i64, now: i64) -> u64 {
+        if end_ts <= start_ts {
+            return total;
+        }
+
+        if now < cliff_ts {
+            return 0;
+        }
+
+        if now >= end_ts {
+            return total;
+        }
+
+        ((total as u128) * ((now - start_ts) as u128) / ((end_ts - start_ts) as u128)) as u64
+    }
+
+    /// Amount unlocked by the linear vesting schedule at `now`. A vault with
+    /// no configured schedule (`end_ts <= start_ts`) is always fully vested.
+    pub fn vested(&self, now: i64) -> u64 {
+        Self::linear_unlock(
+            self.total_deposited,
+            self.start_ts,
+            self.cliff_ts,
+            self.end_ts,
+            now,
+        )
+    }
+
+    /// Same schedule as `vested`, applied to SPL-token deposits instead of
+    /// lamports.
+    pub fn token_vested(&self, now: i64) -> u64 {
+        Self::linear_unlock(
+            self.token_total_deposited,
+            self.start_ts,
+            self.cliff_ts,
+            self.end_ts,
+            now,
+        )
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < LEN || data[..DISCRIMINATOR.len()] != DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut owner: Pubkey = [0u8; 32];
+        owner.copy_from_slice(&data[OWNER_OFFSET..OWNER_OFFSET + size_of::<Pubkey>()]);
+
+        let whitelist_len = data[WHITELIST_LEN_OFFSET];
+
+        let mut whitelist_programs = [[0u8; 32]; MAX_WHITELIST];
+        for (i, entry) in whitelist_programs.iter_mut().enumerate() {
+            let offset = WHITELIST_PROGRAMS_OFFSET + i * size_of::<Pubkey>();
+            entry.copy_from_slice(&data[offset..offset + size_of::<Pubkey>()]);
+        }
+
+        let mut whitelist_destinations = [[0u8; 32]; MAX_WHITELIST];
+        for (i, entry) in whitelist_destinations.iter_mut().enumerate() {
+            let offset = WHITELIST_DESTINATIONS_OFFSET + i * size_of::<Pubkey>();
+            entry.copy_from_slice(&data[offset..offset + size_of::<Pubkey>()]);
+        }
+
+        Ok(Self {
+            owner,
+            bump: data[BUMP_OFFSET],
+            total_deposited: read_u64(data, TOTAL_DEPOSITED_OFFSET),
+            deposit_count: read_u32(data, DEPOSIT_COUNT_OFFSET),
+            released: read_u64(data, RELEASED_OFFSET),
+            token_total_deposited: read_u64(data, TOKEN_TOTAL_DEPOSITED_OFFSET),
+            token_released: read_u64(data, TOKEN_RELEASED_OFFSET),
+            start_ts: read_i64(data, START_TS_OFFSET),
+            cliff_ts: read_i64(data, CLIFF_TS_OFFSET),
+            end_ts: read_i64(data, END_TS_OFFSET),
+            whitelist_len,
+            whitelist_programs,
+            whitelist_destinations,
+        })
+    }
+
+    fn serialize(&self, data: &mut [u8]) {
+        data[..DISCRIMINATOR.len()].copy_from_slice(&DISCRIMINATOR);
+        data[OWNER_OFFSET..OWNER_OFFSET + size_of::<Pubkey>()].copy_from_slice(&self.owner);
+        data[BUMP_OFFSET] = self.bump;
+        write_u64(data, TOTAL_DEPOSITED_OFFSET, self.total_deposited);
+        write_u32(data, DEPOSIT_COUNT_OFFSET, self.deposit_count);
+        write_u64(data, RELEASED_OFFSET, self.released);
+        write_u64(
+            data,
+            TOKEN_TOTAL_DEPOSITED_OFFSET,
+            self.token_total_deposited,
+        );
+        write_u64(data, TOKEN_RELEASED_OFFSET, self.token_released);
+        write_i64(data, START_TS_OFFSET, self.start_ts);
+        write_i64(data, CLIFF_TS_OFFSET, self.cliff_ts);
+        write_i64(data, END_TS_OFFSET, self.end_ts);
+        data[WHITELIST_LEN_OFFSET] = self.whitelist_len;
+        for (i, entry) in self.whitelist_programs.iter().enumerate() {
+            let offset = WHITELIST_PROGRAMS_OFFSET + i * size_of::<Pubkey>();
+            data[offset..offset + size_of::<Pubkey>()].copy_from_slice(entry);
+        }
+        for (i, entry) in self.whitelist_destinations.iter().enumerate() {
+            let offset = WHITELIST_DESTINATIONS_OFFSET + i * size_of::<Pubkey>();
+            data[offset..offset + size_of::<Pubkey>()].copy_from_slice(entry);
+        }
+    }
+
+    /// Reads and validates the vault state without taking an exclusive borrow.
+    pub fn load(vault: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = vault.try_borrow_data()?;
+        Self::deserialize(&data)
+    }
+
+    /// Reads and validates the vault state ahead of a `store` call.
+    pub fn load_mut(vault: &AccountInfo) -> Result<Self, ProgramError> {
+        let data = vault.try_borrow_mut_data()?;
+        Self::deserialize(&data)
+    }
+
+    /// Persists the state back into the vault account's data.
+    pub fn store(&self, vault: &AccountInfo) -> ProgramResult {
+        let mut data = vault.try_borrow_mut_data()?;
+        self.serialize(&mut data);
+        Ok(())
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + size_of::<u64>()].try_into().unwrap())
+}
+
+fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + size_of::<u64>()].copy_from_slice(&value.to_le_bytes());
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + size_of::<u32>()].try_into().unwrap())
+}
+
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + size_of::<u32>()].copy_from_slice(&value.to_le_bytes());
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + size_of::<i64>()].try_into().unwrap())
+}
+
+fn write_i64(data: &mut [u8], offset: usize, value: i64) {
+    data[offset..offset + size_of::<i64>()].copy_from_slice(&value.to_le_bytes());
+}